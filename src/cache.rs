@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use crate::dns::{DnsRequest, ParseError};
+
+/// Identifies a cached response. DNS names are matched case-insensitively
+/// (RFC 1035 section 2.3.3) and the same name can carry distinct records per
+/// `qtype`/`qclass`, so all three fields are needed to avoid e.g. an A-record
+/// lookup being served the cached AAAA answer for the same name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey {
+    name: String,
+    qtype: u16,
+    qclass: u16,
+}
+
+impl CacheKey {
+    pub fn new(name: &str, qtype: u16, qclass: u16) -> CacheKey {
+        CacheKey {
+            name: name.to_ascii_lowercase(),
+            qtype,
+            qclass,
+        }
+    }
+}
+
+/// A cached response plus the bookkeeping needed to age it: the instant it
+/// was cached and the smallest TTL among its answer records, i.e. the point
+/// at which the entry as a whole must be evicted.
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub response_data: Vec<u8>,
+    pub cached_at: Instant,
+    pub min_ttl: u32,
+}
+
+impl CacheEntry {
+    pub fn is_expired(&self, now: Instant) -> bool {
+        now.duration_since(self.cached_at).as_secs() >= self.min_ttl as u64
+    }
+}
+
+pub type Cache = Arc<Mutex<HashMap<CacheKey, CacheEntry>>>;
+
+/// Below this many seconds of remaining TTL, served responses get a jittered
+/// TTL (see `jittered_ttl`) instead of the exact remaining value, so that
+/// downstream resolvers caching the same record don't all expire in lockstep.
+const TTL_JITTER_THRESHOLD_SECS: u32 = 30;
+
+static RNG_STATE: AtomicU64 = AtomicU64::new(0x9E3779B97F4A7C15);
+
+/// A small xorshift64 PRNG. Jitter doesn't need cryptographic randomness,
+/// just enough spread to desynchronize resolvers, so we avoid pulling in a
+/// `rand` dependency for it.
+fn next_jitter_percent() -> u32 {
+    let mut x = RNG_STATE.load(Ordering::Relaxed);
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    RNG_STATE.store(x, Ordering::Relaxed);
+    1 + (x % 10) as u32
+}
+
+fn jittered_ttl(remaining: u32) -> u32 {
+    let reduction = remaining.saturating_mul(next_jitter_percent()) / 100;
+    remaining.saturating_sub(reduction)
+}
+
+/// Parses a raw response packet just far enough to find the minimum TTL
+/// across its answer records, i.e. when the whole cache entry should expire.
+pub fn min_answer_ttl(response_data: &[u8]) -> Result<u32, ParseError> {
+    let mut request = DnsRequest::new();
+    request.parse_response(response_data)?;
+    let data = request.parse_response_data()?;
+    Ok(data.answer_records.iter().map(|record| record.ttl).min().unwrap_or(0))
+}
+
+/// Rewrites a cached entry's record TTLs to reflect how much time has
+/// actually elapsed since it was cached (nearing expiry gets a jittered
+/// value, see `TTL_JITTER_THRESHOLD_SECS`), and stamps the outgoing ID.
+pub fn adjust_response_ttls(entry: &CacheEntry, id: u16) -> Result<Vec<u8>, ParseError> {
+    let mut request = DnsRequest::new();
+    request.parse_response(&entry.response_data)?;
+    let mut data = request.parse_response_data()?;
+    data.id = id;
+
+    let elapsed = entry.cached_at.elapsed().as_secs() as u32;
+    for record in data.answer_records.iter_mut()
+        .chain(data.authority_records.iter_mut())
+        .chain(data.additional_records.iter_mut())
+    {
+        let remaining = record.ttl.saturating_sub(elapsed);
+        record.ttl = if remaining < TTL_JITTER_THRESHOLD_SECS {
+            jittered_ttl(remaining)
+        } else {
+            remaining
+        };
+    }
+
+    let mut writer = DnsRequest::new();
+    writer.binarize_response_data(data);
+    Ok(writer.response_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dns::{DnsResourceRecord, DnsResponseData, RData};
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn adjust_response_ttls_preserves_ra_bit() {
+        let mut writer = DnsRequest::new();
+        writer.qname = "example.com".to_string();
+        writer.qtype = 1;
+        writer.qclass = 1;
+        writer.ancount = 1;
+
+        writer.binarize_response_data(DnsResponseData {
+            id: 1,
+            flags: 0b1000_0001, // QR + RD
+            ra: 1,
+            z: 0,
+            rcode: 0,
+            questions: 1,
+            answers: 1,
+            authority_rrs: 0,
+            additional_rrs: 0,
+            query: ("example.com".to_string(), 1, 1),
+            answer_records: vec![DnsResourceRecord {
+                name: "example.com".to_string(),
+                rr_type: 1,
+                rr_class: 1,
+                ttl: 300,
+                rdata: RData::A(Ipv4Addr::new(1, 2, 3, 4)),
+            }],
+            authority_records: vec![],
+            additional_records: vec![],
+        });
+
+        let entry = CacheEntry {
+            response_data: writer.response_data,
+            cached_at: Instant::now(),
+            min_ttl: 300,
+        };
+
+        // This is exactly the cache-hit rewrite path: re-parsing and
+        // re-binarizing the response to adjust TTLs and stamp a new query
+        // ID must not also zero out header bits it isn't supposed to touch.
+        let rewritten = adjust_response_ttls(&entry, 99).unwrap();
+
+        let mut reader = DnsRequest::new();
+        reader.parse_response(&rewritten).unwrap();
+        assert_eq!(reader.ra, 1);
+    }
+}
@@ -0,0 +1,796 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// Maximum number of compression-pointer jumps allowed while decoding a
+/// single name, mirroring the classic dnsguide `read_qname` safeguard.
+const MAX_COMPRESSION_POINTERS: u8 = 10;
+/// RFC 1035 limits: a label is at most 63 bytes, a full name at most 255.
+const MAX_LABEL_LEN: usize = 63;
+const MAX_NAME_LEN: usize = 255;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    BufferTooShort,
+    LabelTooLong,
+    NameTooLong,
+    PointerOutOfBounds,
+    TooManyCompressionPointers,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::BufferTooShort => write!(f, "buffer too short to contain a valid DNS message"),
+            ParseError::LabelTooLong => write!(f, "label exceeds the 63-byte maximum"),
+            ParseError::NameTooLong => write!(f, "name exceeds the 255-byte maximum"),
+            ParseError::PointerOutOfBounds => write!(f, "compression pointer targets outside the buffer"),
+            ParseError::TooManyCompressionPointers => write!(f, "too many compression pointer jumps"),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Decodes a (possibly compressed) name starting at `*offset`, advancing
+/// `*offset` past it. Pointer chains are capped at `MAX_COMPRESSION_POINTERS`
+/// jumps and every byte touched is bounds checked, so a crafted or cyclic
+/// packet can't hang or panic the server.
+fn parse_name(buf: &[u8], offset: &mut usize) -> Result<String, ParseError> {
+    let mut name = String::new();
+    let mut jumped = false;
+    let mut jump_offset = 0;
+    let mut pointer_jumps = 0u8;
+
+    loop {
+        if *offset >= buf.len() {
+            return Err(ParseError::BufferTooShort);
+        }
+        if buf[*offset] == 0 {
+            break;
+        }
+        if (buf[*offset] & 0b11000000) == 0b11000000 {
+            if *offset + 1 >= buf.len() {
+                return Err(ParseError::BufferTooShort);
+            }
+            pointer_jumps += 1;
+            if pointer_jumps > MAX_COMPRESSION_POINTERS {
+                return Err(ParseError::TooManyCompressionPointers);
+            }
+            if !jumped {
+                jump_offset = *offset + 2;
+            }
+            let target = (((buf[*offset] as u16) & 0b00111111) << 8 | buf[*offset + 1] as u16) as usize;
+            if target >= buf.len() {
+                return Err(ParseError::PointerOutOfBounds);
+            }
+            *offset = target;
+            jumped = true;
+        } else {
+            let len = buf[*offset] as usize;
+            if len > MAX_LABEL_LEN {
+                return Err(ParseError::LabelTooLong);
+            }
+            *offset += 1;
+            if *offset + len > buf.len() {
+                return Err(ParseError::BufferTooShort);
+            }
+            name.push_str(&String::from_utf8_lossy(&buf[*offset..*offset + len]));
+            *offset += len;
+            name.push('.');
+            if name.len() > MAX_NAME_LEN {
+                return Err(ParseError::NameTooLong);
+            }
+        }
+    }
+    if !jumped {
+        *offset += 1;
+    } else {
+        *offset = jump_offset;
+    }
+    name.pop(); // remove the last dot
+    Ok(name)
+}
+
+/// Writes `name` as a sequence of length-prefixed labels terminated by a
+/// null byte, fully expanded with no compression. Used for the outgoing
+/// query, which has a single name and nothing to compress against.
+fn write_name(bin: &mut Vec<u8>, name: &str) {
+    for part in name.split('.') {
+        bin.push(part.len() as u8);
+        bin.extend_from_slice(part.as_bytes());
+    }
+    bin.push(0);
+}
+
+/// Writes `name` using DNS message compression: if `name` (or a suffix of
+/// it) has already been written somewhere in this message, a 2-byte pointer
+/// (`0xC000 | offset`) replaces the repeated labels. `compression` maps
+/// lowercased names to the byte offset they were first written at, and is
+/// shared across the whole message so later sections can point back into
+/// earlier ones.
+fn write_name_compressed(bin: &mut Vec<u8>, name: &str, compression: &mut HashMap<String, u16>) {
+    if name.is_empty() {
+        bin.push(0);
+        return;
+    }
+
+    let key = name.to_ascii_lowercase();
+    if let Some(&offset) = compression.get(&key) {
+        bin.push(0xC0 | (offset >> 8) as u8);
+        bin.push(offset as u8);
+        return;
+    }
+
+    // Pointers are only 14 bits wide, so offsets beyond that can't be
+    // referenced and aren't worth recording.
+    if bin.len() <= 0x3FFF {
+        compression.insert(key, bin.len() as u16);
+    }
+
+    let mut labels = name.splitn(2, '.');
+    let first = labels.next().unwrap();
+    let rest = labels.next();
+
+    bin.push(first.len() as u8);
+    bin.extend_from_slice(first.as_bytes());
+
+    match rest {
+        Some(rest) if !rest.is_empty() => write_name_compressed(bin, rest, compression),
+        _ => bin.push(0),
+    }
+}
+
+fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    (buf[offset] as u16) << 8 | buf[offset + 1] as u16
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    (buf[offset] as u32) << 24 | (buf[offset + 1] as u32) << 16 | (buf[offset + 2] as u32) << 8 | buf[offset + 3] as u32
+}
+
+/// The resource record types this server understands the RDATA layout of.
+/// Anything else round-trips as opaque bytes via `RecordType::Other`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RecordType {
+    A,
+    Ns,
+    Cname,
+    Soa,
+    Ptr,
+    Mx,
+    Txt,
+    Aaaa,
+    Srv,
+    Opt,
+    Other(u16),
+}
+
+impl RecordType {
+    pub fn from_u16(value: u16) -> RecordType {
+        match value {
+            1 => RecordType::A,
+            2 => RecordType::Ns,
+            5 => RecordType::Cname,
+            6 => RecordType::Soa,
+            12 => RecordType::Ptr,
+            15 => RecordType::Mx,
+            16 => RecordType::Txt,
+            28 => RecordType::Aaaa,
+            33 => RecordType::Srv,
+            41 => RecordType::Opt,
+            other => RecordType::Other(other),
+        }
+    }
+
+    pub fn to_u16(self) -> u16 {
+        match self {
+            RecordType::A => 1,
+            RecordType::Ns => 2,
+            RecordType::Cname => 5,
+            RecordType::Soa => 6,
+            RecordType::Ptr => 12,
+            RecordType::Mx => 15,
+            RecordType::Txt => 16,
+            RecordType::Aaaa => 28,
+            RecordType::Srv => 33,
+            RecordType::Opt => 41,
+            RecordType::Other(value) => value,
+        }
+    }
+}
+
+/// A decoded RDATA value. Structured per record type so the server can
+/// inspect and rewrite answers instead of treating RDATA as opaque bytes.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RData {
+    A(Ipv4Addr),
+    Ns(String),
+    Cname(String),
+    Soa {
+        mname: String,
+        rname: String,
+        serial: u32,
+        refresh: u32,
+        retry: u32,
+        expire: u32,
+        minimum: u32,
+    },
+    Ptr(String),
+    Mx { preference: u16, exchange: String },
+    Txt(Vec<String>),
+    Aaaa(Ipv6Addr),
+    Srv { priority: u16, weight: u16, port: u16, target: String },
+    Opt(Vec<u8>),
+    Unknown(Vec<u8>),
+}
+
+impl RData {
+    /// Decodes `rdlength` bytes of RDATA starting at `*offset` according to
+    /// `record_type`. Domain-name fields may use compression pointers that
+    /// jump elsewhere in `buf`; the caller is expected to reset `*offset` to
+    /// the RDATA's end (`start + rdlength`) afterwards rather than trust the
+    /// value this function leaves it at, since a pointer can make that
+    /// shorter than the on-wire RDATA.
+    pub fn parse(record_type: RecordType, buf: &[u8], offset: &mut usize, rdlength: usize) -> Result<RData, ParseError> {
+        let start = *offset;
+        let end = start.checked_add(rdlength).ok_or(ParseError::BufferTooShort)?;
+        if end > buf.len() {
+            return Err(ParseError::BufferTooShort);
+        }
+
+        match record_type {
+            RecordType::A => {
+                if rdlength != 4 {
+                    return Err(ParseError::BufferTooShort);
+                }
+                *offset = end;
+                Ok(RData::A(Ipv4Addr::new(buf[start], buf[start + 1], buf[start + 2], buf[start + 3])))
+            }
+            RecordType::Aaaa => {
+                if rdlength != 16 {
+                    return Err(ParseError::BufferTooShort);
+                }
+                let mut octets = [0u8; 16];
+                octets.copy_from_slice(&buf[start..end]);
+                *offset = end;
+                Ok(RData::Aaaa(Ipv6Addr::from(octets)))
+            }
+            RecordType::Ns => Ok(RData::Ns(parse_name(buf, offset)?)),
+            RecordType::Cname => Ok(RData::Cname(parse_name(buf, offset)?)),
+            RecordType::Ptr => Ok(RData::Ptr(parse_name(buf, offset)?)),
+            RecordType::Mx => {
+                if rdlength < 2 {
+                    return Err(ParseError::BufferTooShort);
+                }
+                let preference = read_u16(buf, start);
+                *offset = start + 2;
+                let exchange = parse_name(buf, offset)?;
+                Ok(RData::Mx { preference, exchange })
+            }
+            RecordType::Soa => {
+                let mname = parse_name(buf, offset)?;
+                let rname = parse_name(buf, offset)?;
+                if *offset + 20 > buf.len() {
+                    return Err(ParseError::BufferTooShort);
+                }
+                let serial = read_u32(buf, *offset);
+                let refresh = read_u32(buf, *offset + 4);
+                let retry = read_u32(buf, *offset + 8);
+                let expire = read_u32(buf, *offset + 12);
+                let minimum = read_u32(buf, *offset + 16);
+                *offset += 20;
+                Ok(RData::Soa { mname, rname, serial, refresh, retry, expire, minimum })
+            }
+            RecordType::Txt => {
+                let mut strings = Vec::new();
+                let mut i = start;
+                while i < end {
+                    let len = buf[i] as usize;
+                    i += 1;
+                    if i + len > end {
+                        return Err(ParseError::BufferTooShort);
+                    }
+                    strings.push(String::from_utf8_lossy(&buf[i..i + len]).into_owned());
+                    i += len;
+                }
+                *offset = end;
+                Ok(RData::Txt(strings))
+            }
+            RecordType::Srv => {
+                if rdlength < 6 {
+                    return Err(ParseError::BufferTooShort);
+                }
+                let priority = read_u16(buf, start);
+                let weight = read_u16(buf, start + 2);
+                let port = read_u16(buf, start + 4);
+                *offset = start + 6;
+                let target = parse_name(buf, offset)?;
+                Ok(RData::Srv { priority, weight, port, target })
+            }
+            RecordType::Opt => {
+                *offset = end;
+                Ok(RData::Opt(buf[start..end].to_vec()))
+            }
+            RecordType::Other(_) => {
+                *offset = end;
+                Ok(RData::Unknown(buf[start..end].to_vec()))
+            }
+        }
+    }
+
+    /// Encodes the RDATA value to its wire representation, writing directly
+    /// into `bin` (the in-progress message) so that any domain names it
+    /// contains can participate in the same message-wide compression as
+    /// record names.
+    pub fn write(&self, bin: &mut Vec<u8>, compression: &mut HashMap<String, u16>) {
+        match self {
+            RData::A(addr) => bin.extend_from_slice(&addr.octets()),
+            RData::Aaaa(addr) => bin.extend_from_slice(&addr.octets()),
+            RData::Ns(name) | RData::Cname(name) | RData::Ptr(name) => write_name_compressed(bin, name, compression),
+            RData::Mx { preference, exchange } => {
+                bin.push((preference >> 8) as u8);
+                bin.push(*preference as u8);
+                write_name_compressed(bin, exchange, compression);
+            }
+            RData::Soa { mname, rname, serial, refresh, retry, expire, minimum } => {
+                write_name_compressed(bin, mname, compression);
+                write_name_compressed(bin, rname, compression);
+                for field in [serial, refresh, retry, expire, minimum] {
+                    bin.push((field >> 24) as u8);
+                    bin.push((field >> 16) as u8);
+                    bin.push((field >> 8) as u8);
+                    bin.push(*field as u8);
+                }
+            }
+            RData::Txt(strings) => {
+                for s in strings {
+                    bin.push(s.len() as u8);
+                    bin.extend_from_slice(s.as_bytes());
+                }
+            }
+            RData::Srv { priority, weight, port, target } => {
+                for field in [priority, weight, port] {
+                    bin.push((field >> 8) as u8);
+                    bin.push(*field as u8);
+                }
+                write_name_compressed(bin, target, compression);
+            }
+            RData::Opt(raw) | RData::Unknown(raw) => bin.extend_from_slice(raw),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DnsRequest {
+    pub id: u16,
+    pub qr: u8,
+    pub opcode: u8,
+    pub aa: u8,
+    pub tc: u8,
+    pub rd: u8,
+    pub ra: u8,
+    pub z: u8,
+    pub rcode: u8,
+    pub qdcount: u16,
+    pub ancount: u16,
+    pub nscount: u16,
+    pub arcount: u16,
+    pub qname: String,
+    pub qtype: u16,
+    pub qclass: u16,
+    pub response_data: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DnsResourceRecord {
+    pub name: String,
+    pub rr_type: u16,
+    pub rr_class: u16,
+    pub ttl: u32,
+    pub rdata: RData,
+}
+
+#[derive(Debug, Clone)]
+pub struct DnsResponseData {
+    pub id: u16,
+    pub flags: u8,
+    pub ra: u8,
+    pub z: u8,
+    pub rcode: u8,
+    pub questions: u16,
+    pub answers: u16,
+    pub authority_rrs: u16,
+    pub additional_rrs: u16,
+    pub query: (String, u16, u16),
+    pub answer_records: Vec<DnsResourceRecord>,
+    pub authority_records: Vec<DnsResourceRecord>,
+    pub additional_records: Vec<DnsResourceRecord>,
+}
+
+
+impl DnsRequest {
+    pub fn new() -> DnsRequest {
+        DnsRequest {
+            id: 0,
+            qr: 0,
+            opcode: 0,
+            aa: 0,
+            tc: 0,
+            rd: 0,
+            ra: 0,
+            z: 0,
+            rcode: 0,
+            qdcount: 0,
+            ancount: 0,
+            nscount: 0,
+            arcount: 0,
+            qname: String::from(""),
+            qtype: 0,
+            qclass: 0,
+            response_data: Vec::new(),
+        }
+    }
+
+    pub fn parse(&mut self, buf: &[u8]) -> Result<(), ParseError> {
+        if buf.len() < 12 {
+            return Err(ParseError::BufferTooShort);
+        }
+        self.id = (buf[0] as u16) << 8 | buf[1] as u16;
+        self.qr = (buf[2] & 0b10000000) >> 7;
+        self.opcode = (buf[2] & 0b01111000) >> 3;
+        self.aa = (buf[2] & 0b00000100) >> 2;
+        self.tc = (buf[2] & 0b00000010) >> 1;
+        self.rd = buf[2] & 0b00000001;
+        self.ra = (buf[3] & 0b10000000) >> 7;
+        self.z = (buf[3] & 0b01110000) >> 4;
+        self.rcode = buf[3] & 0b00001111;
+        self.qdcount = (buf[4] as u16) << 8 | buf[5] as u16;
+        self.ancount = (buf[6] as u16) << 8 | buf[7] as u16;
+        self.nscount = (buf[8] as u16) << 8 | buf[9] as u16;
+        self.arcount = (buf[10] as u16) << 8 | buf[11] as u16;
+
+        let mut i = 12;
+        while i < buf.len() && buf[i] != 0 {
+            let mut j = buf[i] as usize;
+            if j > MAX_LABEL_LEN {
+                return Err(ParseError::LabelTooLong);
+            }
+            i += 1;
+            if i + j > buf.len() {
+                return Err(ParseError::BufferTooShort);
+            }
+            while j > 0 {
+                self.qname.push(buf[i] as char);
+                i += 1;
+                j -= 1;
+            }
+            self.qname.push('.');
+            if self.qname.len() > MAX_NAME_LEN {
+                return Err(ParseError::NameTooLong);
+            }
+        }
+        self.qname.pop(); // remove the last dot
+        i += 1;
+
+        if i + 4 > buf.len() {
+            return Err(ParseError::BufferTooShort);
+        }
+        self.qtype = (buf[i] as u16) << 8 | buf[i + 1] as u16;
+        self.qclass = (buf[i + 2] as u16) << 8 | buf[i + 3] as u16;
+
+        Ok(())
+    }
+
+    pub fn parse_response(&mut self, buf: &[u8]) -> Result<(), ParseError> {
+        self.parse(buf)?;
+        self.response_data = buf.to_vec();
+        Ok(())
+    }
+
+
+    pub fn binarize(&self) -> Vec<u8> {
+        if !self.response_data.is_empty() {
+            let mut response = self.response_data.clone();
+            response[0] = (self.id >> 8) as u8;
+            response[1] = self.id as u8;
+            return response;
+        }
+
+        let mut bin = vec![
+            (self.id >> 8) as u8,
+            self.id as u8,
+            (self.qr << 7) | (self.opcode << 3) | (self.aa << 2) | (self.tc << 1) | self.rd,
+            (self.ra << 7) | (self.z << 4) | self.rcode,
+            (self.qdcount >> 8) as u8,
+            self.qdcount as u8,
+            (self.ancount >> 8) as u8,
+            self.ancount as u8,
+            (self.nscount >> 8) as u8,
+            self.nscount as u8,
+            (self.arcount >> 8) as u8,
+            self.arcount as u8,
+        ];
+        write_name(&mut bin, &self.qname);
+        bin.push((self.qtype >> 8) as u8);
+        bin.push(self.qtype as u8);
+        bin.push((self.qclass >> 8) as u8);
+        bin.push(self.qclass as u8);
+        bin
+    }
+
+    fn parse_resource_record(&self, buf: &[u8], offset: &mut usize) -> Result<DnsResourceRecord, ParseError> {
+        let name = parse_name(buf, offset)?;
+        if *offset + 10 > buf.len() {
+            return Err(ParseError::BufferTooShort);
+        }
+        let rr_type = read_u16(buf, *offset);
+        let rr_class = read_u16(buf, *offset + 2);
+        let ttl = read_u32(buf, *offset + 4);
+        let rdlength = read_u16(buf, *offset + 8) as usize;
+        *offset += 10;
+
+        if *offset + rdlength > buf.len() {
+            return Err(ParseError::BufferTooShort);
+        }
+        let rdata_start = *offset;
+        let mut rdata_offset = rdata_start;
+        let rdata = RData::parse(RecordType::from_u16(rr_type), buf, &mut rdata_offset, rdlength)?;
+        *offset = rdata_start + rdlength;
+
+        Ok(DnsResourceRecord {
+            name,
+            rr_type,
+            rr_class,
+            ttl,
+            rdata,
+        })
+    }
+
+    /// Parses the answer/authority/additional sections out of `buf`, which
+    /// must start with the same header and question this request was
+    /// parsed from. Used both for `self.response_data` (a cached response)
+    /// and for a freshly-received query buffer, e.g. to inspect its EDNS0
+    /// OPT record.
+    pub fn parse_sections(&self, buf: &[u8]) -> Result<DnsResponseData, ParseError> {
+        let mut offset = 12;
+        let flags = (self.qr << 7) | (self.opcode << 3) | (self.aa << 2) | (self.tc << 1) | self.rd;
+
+        offset += self.qname.len() + 2 + 4; // Skip over the question section
+
+        let mut answer_records = Vec::new();
+        for _ in 0..self.ancount {
+            let rr = self.parse_resource_record(buf, &mut offset)?;
+            answer_records.push(rr);
+        }
+
+        let mut authority_records = Vec::new();
+        for _ in 0..self.nscount {
+            let rr = self.parse_resource_record(buf, &mut offset)?;
+            authority_records.push(rr);
+        }
+
+        let mut additional_records = Vec::new();
+        for _ in 0..self.arcount {
+            let rr = self.parse_resource_record(buf, &mut offset)?;
+            additional_records.push(rr);
+        }
+
+        Ok(DnsResponseData {
+            id: self.id,
+            flags,
+            ra: self.ra,
+            z: self.z,
+            rcode: self.rcode,
+            questions: self.qdcount,
+            answers: self.ancount,
+            authority_rrs: self.nscount,
+            additional_rrs: self.arcount,
+            query: (self.qname.clone(), self.qtype, self.qclass),
+            answer_records,
+            authority_records,
+            additional_records,
+        })
+    }
+
+    pub fn parse_response_data(&self) -> Result<DnsResponseData, ParseError> {
+        self.parse_sections(&self.response_data)
+    }
+
+    pub fn binarize_response_data(&mut self, body: DnsResponseData) {
+        // Header
+        let mut bin = vec![
+            (body.id >> 8) as u8,
+            body.id as u8,
+            body.flags,
+            (body.ra << 7) | (body.z << 4) | (body.rcode & 0b0000_1111),
+            (body.questions >> 8) as u8,
+            body.questions as u8,
+            (body.answers >> 8) as u8,
+            body.answers as u8,
+            (body.authority_rrs >> 8) as u8,
+            body.authority_rrs as u8,
+            (body.additional_rrs >> 8) as u8,
+            body.additional_rrs as u8,
+        ];
+
+        // Question section. `compression` is shared across every section
+        // below so later names can point back at this (or any earlier) one.
+        let mut compression: HashMap<String, u16> = HashMap::new();
+        write_name_compressed(&mut bin, &body.query.0, &mut compression);
+        bin.push((body.query.1 >> 8) as u8);
+        bin.push(body.query.1 as u8);
+        bin.push((body.query.2 >> 8) as u8);
+        bin.push(body.query.2 as u8);
+
+        // Answer section
+        for record in body.answer_records {
+            self.binarize_record(&record, &mut bin, &mut compression);
+        }
+
+        // Authority section
+        for record in body.authority_records {
+            self.binarize_record(&record, &mut bin, &mut compression);
+        }
+
+        // Additional section
+        for record in body.additional_records {
+            self.binarize_record(&record, &mut bin, &mut compression);
+        }
+
+        self.response_data = bin;
+    }
+
+    fn binarize_record(&self, record: &DnsResourceRecord, bin: &mut Vec<u8>, compression: &mut HashMap<String, u16>) {
+        // Name
+        write_name_compressed(bin, &record.name, compression);
+
+        // Type
+        bin.push((record.rr_type >> 8) as u8);
+        bin.push(record.rr_type as u8);
+
+        // Class
+        bin.push((record.rr_class >> 8) as u8);
+        bin.push(record.rr_class as u8);
+
+        // TTL
+        bin.push((record.ttl >> 24) as u8);
+        bin.push((record.ttl >> 16) as u8);
+        bin.push((record.ttl >> 8) as u8);
+        bin.push(record.ttl as u8);
+
+        // RDATA: RDLENGTH precedes it on the wire but isn't known until
+        // after encoding (names inside may compress to pointers), so write
+        // a placeholder and patch it in once the real length is known.
+        let rdlength_pos = bin.len();
+        bin.push(0);
+        bin.push(0);
+        let rdata_start = bin.len();
+        record.rdata.write(bin, compression);
+        let rdata_len = bin.len() - rdata_start;
+        bin[rdlength_pos] = (rdata_len >> 8) as u8;
+        bin[rdlength_pos + 1] = rdata_len as u8;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_name_rejects_cyclic_compression_pointer() {
+        // A name at offset 12 that points right back at itself: every jump
+        // looks like progress (it decodes a new pointer each time) but never
+        // terminates, so this must be caught by the jump-count cap rather
+        // than hanging the server.
+        let mut buf = vec![0u8; 12];
+        buf.push(0xC0);
+        buf.push(12);
+
+        let mut offset = 12;
+        assert_eq!(parse_name(&buf, &mut offset), Err(ParseError::TooManyCompressionPointers));
+    }
+
+    fn rdata_roundtrip(record_type: RecordType, rdata: RData) {
+        let mut bin = Vec::new();
+        let mut compression = HashMap::new();
+        rdata.write(&mut bin, &mut compression);
+
+        let mut offset = 0;
+        let parsed = RData::parse(record_type, &bin, &mut offset, bin.len()).unwrap();
+        assert_eq!(parsed, rdata);
+    }
+
+    #[test]
+    fn rdata_a_round_trips() {
+        rdata_roundtrip(RecordType::A, RData::A(Ipv4Addr::new(93, 184, 216, 34)));
+    }
+
+    #[test]
+    fn rdata_aaaa_round_trips() {
+        rdata_roundtrip(RecordType::Aaaa, RData::Aaaa(Ipv6Addr::new(0x2606, 0x2800, 0x220, 1, 0x248, 0x1893, 0x25c8, 0x1946)));
+    }
+
+    #[test]
+    fn rdata_cname_round_trips() {
+        rdata_roundtrip(RecordType::Cname, RData::Cname("example.com".to_string()));
+    }
+
+    #[test]
+    fn rdata_mx_round_trips() {
+        rdata_roundtrip(RecordType::Mx, RData::Mx { preference: 10, exchange: "mail.example.com".to_string() });
+    }
+
+    #[test]
+    fn rdata_soa_round_trips() {
+        rdata_roundtrip(RecordType::Soa, RData::Soa {
+            mname: "ns1.example.com".to_string(),
+            rname: "admin.example.com".to_string(),
+            serial: 2024010100,
+            refresh: 3600,
+            retry: 900,
+            expire: 604800,
+            minimum: 86400,
+        });
+    }
+
+    #[test]
+    fn rdata_txt_round_trips() {
+        rdata_roundtrip(RecordType::Txt, RData::Txt(vec!["v=spf1 -all".to_string()]));
+    }
+
+    #[test]
+    fn binarize_response_data_compresses_repeated_names_and_round_trips() {
+        let mut writer = DnsRequest::new();
+        writer.id = 42;
+        writer.qname = "example.com".to_string();
+        writer.qtype = 1;
+        writer.qclass = 1;
+        writer.ancount = 2;
+
+        let body = DnsResponseData {
+            id: 42,
+            flags: 0b1000_0100,
+            ra: 1,
+            z: 0,
+            rcode: 0,
+            questions: 1,
+            answers: 2,
+            authority_rrs: 0,
+            additional_rrs: 0,
+            query: ("example.com".to_string(), 1, 1),
+            answer_records: vec![
+                DnsResourceRecord {
+                    name: "example.com".to_string(),
+                    rr_type: 1,
+                    rr_class: 1,
+                    ttl: 300,
+                    rdata: RData::A(Ipv4Addr::new(1, 2, 3, 4)),
+                },
+                DnsResourceRecord {
+                    name: "example.com".to_string(),
+                    rr_type: 1,
+                    rr_class: 1,
+                    ttl: 300,
+                    rdata: RData::A(Ipv4Addr::new(5, 6, 7, 8)),
+                },
+            ],
+            authority_records: vec![],
+            additional_records: vec![],
+        };
+
+        writer.binarize_response_data(body);
+
+        // Both answer names repeat the question's "example.com" and should
+        // compress down to a 2-byte pointer each, rather than being spelled
+        // out in full a second and third time.
+        assert_eq!(writer.response_data.len(), 61);
+
+        let parsed = writer.parse_response_data().unwrap();
+        assert_eq!(parsed.answer_records.len(), 2);
+        assert_eq!(parsed.answer_records[0].name, "example.com");
+        assert_eq!(parsed.answer_records[0].rdata, RData::A(Ipv4Addr::new(1, 2, 3, 4)));
+        assert_eq!(parsed.answer_records[1].name, "example.com");
+        assert_eq!(parsed.answer_records[1].rdata, RData::A(Ipv4Addr::new(5, 6, 7, 8)));
+    }
+}
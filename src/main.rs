@@ -1,458 +1,264 @@
-use std::{collections::HashMap, fs, net::{Ipv4Addr, SocketAddrV4, UdpSocket}, sync::{Arc, Mutex}};
+mod cache;
+mod dns;
+mod resolver;
+mod zone;
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{Ipv4Addr, SocketAddrV4, TcpListener, TcpStream, UdpSocket};
+use std::sync::{Arc, Mutex};
+use std::thread;
 use std::time::{Duration, Instant};
 use async_std::task;
 
+use cache::{adjust_response_ttls, min_answer_ttl, Cache, CacheEntry, CacheKey};
+use dns::{DnsRequest, DnsResponseData, RecordType};
+use resolver::{ResolverPool, Upstream};
+use zone::ZoneStore;
 
-#[derive(Debug, Clone)]
-struct DnsRequest {
-    id: u16,
-    qr: u8,
-    opcode: u8,
-    aa: u8,
-    tc: u8,
-    rd: u8,
-    ra: u8,
-    z: u8,
-    rcode: u8,
-    qdcount: u16,
-    ancount: u16,
-    nscount: u16,
-    arcount: u16,
-    qname: String,
-    qtype: u16,
-    qclass: u16,
-    response_data: Vec<u8>,
-}
+/// How long to wait on a single upstream before failing over to the next.
+const UPSTREAM_TIMEOUT: Duration = Duration::from_secs(2);
 
-#[derive(Debug, Clone)]
-struct DnsResourceRecord {
-    name: String,
-    rr_type: u16,
-    rr_class: u16,
-    ttl: u32,
-    rdlength: u16,
-    rdata: Vec<u8>,
-}
+/// Default max UDP response size per RFC 1035, used when a query carries no
+/// EDNS0 OPT record advertising a larger one.
+const DEFAULT_UDP_PAYLOAD_SIZE: u16 = 512;
 
-#[derive(Debug, Clone)]
-struct DnsResponseData {
-    id: u16,
-    flags: u8,
-    questions: u16,
-    answers: u16,
-    authority_rrs: u16,
-    additional_rrs: u16,
-    query: (String, u16, u16),
-    answer_records: Vec<DnsResourceRecord>,
-    authority_records: Vec<DnsResourceRecord>,
-    additional_records: Vec<DnsResourceRecord>,
-}
+/// Path to the local zone file (see `zone::ZoneStore::load`). Missing or
+/// empty is fine: the server just has no authoritative zones configured.
+const ZONE_FILE_PATH: &str = "zones.conf";
 
-
-impl DnsRequest {
-    fn new() -> DnsRequest {
-        DnsRequest {
-            id: 0,
-            qr: 0,
-            opcode: 0,
-            aa: 0,
-            tc: 0,
-            rd: 0,
-            ra: 0,
-            z: 0,
-            rcode: 0,
-            qdcount: 0,
-            ancount: 0,
-            nscount: 0,
-            arcount: 0,
-            qname: String::from(""),
-            qtype: 0,
-            qclass: 0,
-            response_data: Vec::new(),
-        }
+async fn remove_expired_cache(cache: Cache) {
+    loop {
+        task::sleep(Duration::from_secs(1)).await;
+        let mut cache = cache.lock().unwrap();
+        let now = Instant::now();
+        cache.retain(|_, entry| !entry.is_expired(now));
     }
+}
 
-    fn parse(&mut self, buf: &[u8]) {
-        if buf.len() < 12 {
-            panic!("Buffer too short to contain a DNS header");
-        }
-        self.id = (buf[0] as u16) << 8 | buf[1] as u16;
-        self.qr = (buf[2] & 0b10000000) >> 7;
-        self.opcode = (buf[2] & 0b01111000) >> 3;
-        self.aa = (buf[2] & 0b00000100) >> 2;
-        self.tc = (buf[2] & 0b00000010) >> 1;
-        self.rd = buf[2] & 0b00000001;
-        self.ra = (buf[3] & 0b10000000) >> 7;
-        self.z = (buf[3] & 0b01110000) >> 4;
-        self.rcode = buf[3] & 0b00001111;
-        self.qdcount = (buf[4] as u16) << 8 | buf[5] as u16;
-        self.ancount = (buf[6] as u16) << 8 | buf[7] as u16;
-        self.nscount = (buf[8] as u16) << 8 | buf[9] as u16;
-        self.arcount = (buf[10] as u16) << 8 | buf[11] as u16;
-
-        let mut i = 12;
-        while i < buf.len() && buf[i] != 0 {
-            let mut j = buf[i] as usize;
-            i += 1;
-            if i + j > buf.len() {
-                panic!("Buffer too short to contain qname part");
-            }
-            while j > 0 {
-                self.qname.push(buf[i] as char);
-                i += 1;
-                j -= 1;
-            }
-            self.qname.push('.');
-        }
-        self.qname.pop(); // remove the last dot
-        i += 1;
+/// Builds a minimal response echoing `request`'s question with no answers,
+/// used when every upstream resolver failed. Whatever asked can retry; this
+/// at least avoids the server hanging or panicking for a client that timed
+/// out waiting on a dead forwarder.
+fn servfail_response(request: &DnsRequest) -> Vec<u8> {
+    let data = DnsResponseData {
+        id: request.id,
+        flags: (1 << 7) | (request.opcode << 3) | request.rd,
+        ra: 1, // this server does support recursion; it just failed this time
+        z: 0,
+        rcode: 2, // SERVFAIL
+        questions: 1,
+        answers: 0,
+        authority_rrs: 0,
+        additional_rrs: 0,
+        query: (request.qname.clone(), request.qtype, request.qclass),
+        answer_records: vec![],
+        authority_records: vec![],
+        additional_records: vec![],
+    };
+    let mut writer = DnsRequest::new();
+    writer.binarize_response_data(data);
+    writer.response_data
+}
 
-        if i + 4 > buf.len() {
-            panic!("Buffer too short to contain qtype and qclass");
+/// Forwards `request` to the upstream resolver pool, caches the answer keyed
+/// by `cache_key` with its minimum answer TTL, and returns the raw response.
+fn forward_and_cache(resolver: &ResolverPool, request: &DnsRequest, cache: &Cache, cache_key: CacheKey) -> Vec<u8> {
+    let response = match resolver.resolve(&request.binarize()) {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!("All upstreams failed for {}: {}", request.qname, e);
+            return servfail_response(request);
         }
-        self.qtype = (buf[i] as u16) << 8 | buf[i + 1] as u16;
-        self.qclass = (buf[i + 2] as u16) << 8 | buf[i + 3] as u16;
-        
-    }
-
-    fn parse_response(&mut self, buf: &[u8]) {
-        self.parse(buf);
-        self.response_data = buf.to_vec();
-    }
+    };
 
+    let min_ttl = min_answer_ttl(&response).unwrap_or(0);
+    let entry = CacheEntry {
+        response_data: response.clone(),
+        cached_at: Instant::now(),
+        min_ttl,
+    };
+    cache.lock().unwrap().insert(cache_key, entry);
 
-    fn binarize(&self) -> Vec<u8> {
-        if !self.response_data.is_empty() {
-            let mut response = self.response_data.clone();
-            response[0] = (self.id >> 8) as u8;
-            response[1] = self.id as u8;
-            return response;
-        }
+    response
+}
 
-        let mut bin = Vec::new();
-        bin.push((self.id >> 8) as u8);
-        bin.push(self.id as u8);
-        bin.push((self.qr << 7) | (self.opcode << 3) | (self.aa << 2) | (self.tc << 1) | self.rd);
-        bin.push((self.ra << 7) | (self.z << 4) | self.rcode);
-        bin.push((self.qdcount >> 8) as u8);
-        bin.push(self.qdcount as u8);
-        bin.push((self.ancount >> 8) as u8);
-        bin.push(self.ancount as u8);
-        bin.push((self.nscount >> 8) as u8);
-        bin.push(self.nscount as u8);
-        bin.push((self.arcount >> 8) as u8);
-        bin.push(self.arcount as u8);
-        for part in self.qname.split('.') {
-            bin.push(part.len() as u8);
-            for &b in part.as_bytes() {
-                bin.push(b);
-            }
-        }
-        bin.push(0);
-        bin.push((self.qtype >> 8) as u8);
-        bin.push(self.qtype as u8);
-        bin.push((self.qclass >> 8) as u8);
-        bin.push(self.qclass as u8);
-        bin
+/// Answers `request` authoritatively if its name falls within a configured
+/// local zone, from the cache if a fresh entry exists, or by forwarding to
+/// the upstream resolver pool (and caching the result) otherwise. Shared by
+/// both the UDP and TCP serving loops.
+fn resolve_query(request: &DnsRequest, cache_key: &CacheKey, resolver: &ResolverPool, cache: &Cache, zones: &ZoneStore) -> Vec<u8> {
+    if let Some(zone) = zones.find(&request.qname) {
+        let mut writer = DnsRequest::new();
+        writer.binarize_response_data(zone.answer(request));
+        return writer.response_data;
     }
 
-    fn parse_resource_record(&self, buf: &[u8], offset: &mut usize) -> DnsResourceRecord {
-        let name = self.parse_name(buf, offset);
-        let rr_type = (buf[*offset] as u16) << 8 | buf[*offset + 1] as u16;
-        let rr_class = (buf[*offset + 2] as u16) << 8 | buf[*offset + 3] as u16;
-        let ttl = (buf[*offset + 4] as u32) << 24 | (buf[*offset + 5] as u32) << 16 | (buf[*offset + 6] as u32) << 8 | buf[*offset + 7] as u32;
-        let rdlength = (buf[*offset + 8] as u16) << 8 | buf[*offset + 9] as u16;
-        *offset += 10;
-
-        let rdata = buf[*offset..(*offset + rdlength as usize)].to_vec();
-        *offset += rdlength as usize;
-
-        DnsResourceRecord {
-            name,
-            rr_type,
-            rr_class,
-            ttl,
-            rdlength,
-            rdata,
+    let cached_entry = {
+        let mut cache = cache.lock().unwrap();
+        match cache.get(cache_key) {
+            Some(entry) if !entry.is_expired(Instant::now()) => Some(entry.clone()),
+            Some(_) => {
+                cache.remove(cache_key);
+                None
+            }
+            None => None,
         }
-    }
+    };
 
-    fn parse_name(&self, buf: &[u8], offset: &mut usize) -> String {
-        let mut name = String::new();
-        let mut jumped = false;
-        let mut jump_offset = 0;
-
-        while buf[*offset] != 0 {
-            if (buf[*offset] & 0b11000000) == 0b11000000 {
-                if !jumped {
-                    jump_offset = *offset + 2;
-                }
-                *offset = (((buf[*offset] as u16) & 0b00111111) << 8 | buf[*offset + 1] as u16) as usize;
-                jumped = true;
-            } else {
-                let len = buf[*offset] as usize;
-                *offset += 1;
-                name.push_str(&String::from_utf8_lossy(&buf[*offset..*offset + len]));
-                *offset += len;
-                name.push('.');
+    match cached_entry {
+        Some(entry) => match adjust_response_ttls(&entry, request.id) {
+            Ok(resp) => resp,
+            Err(e) => {
+                eprintln!("Dropping corrupt cache entry for {}: {}", request.qname, e);
+                cache.lock().unwrap().remove(cache_key);
+                forward_and_cache(resolver, request, cache, cache_key.clone())
             }
-        }
-        if !jumped {
-            *offset += 1;
-        } else {
-            *offset = jump_offset;
-        }
-        name.pop(); // remove the last dot
-        name
+        },
+        None => forward_and_cache(resolver, request, cache, cache_key.clone()),
     }
+}
 
-    fn parse_response_data(&self) -> DnsResponseData {
-        let mut offset = 12;
-        let flags = (self.qr << 7) | (self.opcode << 3) | (self.aa << 2) | (self.tc << 1) | self.rd;
-
-        offset += self.qname.len() + 2 + 4; // Skip over the question section
-        
-        let mut answer_records = Vec::new();
-        for _ in 0..self.ancount {
-            let rr = self.parse_resource_record(&self.response_data, &mut offset);
-            answer_records.push(rr);
-        }
-
-        let mut authority_records = Vec::new();
-        for _ in 0..self.nscount {
-            let rr = self.parse_resource_record(&self.response_data, &mut offset);
-            authority_records.push(rr);
-        }
+/// Reads the requestor's advertised UDP payload size from an EDNS0 OPT
+/// pseudo-record in `buf`'s additional section (RFC 6891: the size rides in
+/// the record's class field), falling back to the RFC 1035 default of 512
+/// when the query carries no OPT record or fails to parse.
+fn client_udp_payload_size(request: &DnsRequest, buf: &[u8]) -> u16 {
+    request.parse_sections(buf)
+        .ok()
+        .and_then(|data| data.additional_records.iter()
+            .find(|record| RecordType::from_u16(record.rr_type) == RecordType::Opt)
+            .map(|record| record.rr_class))
+        .unwrap_or(DEFAULT_UDP_PAYLOAD_SIZE)
+}
 
-        let mut additional_records = Vec::new();
-        for _ in 0..self.arcount {
-            let rr = self.parse_resource_record(&self.response_data, &mut offset);
-            additional_records.push(rr);
-        }
+/// Truncates `response` to fit `max_size` for UDP delivery, per the classic
+/// (pre-EDNS) fallback: drop every record, set the TC bit, and let the
+/// client retry the same query over TCP to get the full answer.
+fn truncate_for_udp(response: &[u8], max_size: usize) -> Vec<u8> {
+    if response.len() <= max_size {
+        return response.to_vec();
+    }
 
-        DnsResponseData {
-            id: self.id,
-            flags,
-            questions: self.qdcount,
-            answers: self.ancount,
-            authority_rrs: self.nscount,
-            additional_rrs: self.arcount,
-            query: (self.qname.clone(), self.qtype, self.qclass),
-            answer_records,
-            authority_records,
-            additional_records,
+    let mut request = DnsRequest::new();
+    let data = request.parse_response(response).ok()
+        .and_then(|_| request.parse_response_data().ok());
+
+    match data {
+        Some(mut data) => {
+            data.flags |= 0b0000_0010; // TC bit
+            data.answers = 0;
+            data.authority_rrs = 0;
+            data.additional_rrs = 0;
+            data.answer_records.clear();
+            data.authority_records.clear();
+            data.additional_records.clear();
+
+            let mut writer = DnsRequest::new();
+            writer.binarize_response_data(data);
+            writer.response_data
         }
+        // Can't safely rebuild a truncated message out of something that
+        // doesn't even parse; a raw byte cut is still better than a reply
+        // bigger than what the client asked to receive over UDP.
+        None => response[..max_size].to_vec(),
     }
+}
 
-    fn binarize_response_data(&mut self, body: DnsResponseData){
-        let mut bin = Vec::new();
-
-        // Header
-        bin.push((body.id >> 8) as u8);
-        bin.push(body.id as u8);
-        bin.push(body.flags);
-        bin.push(0); // Placeholder for the second byte of flags and rcode
-        bin.push((body.questions >> 8) as u8);
-        bin.push(body.questions as u8);
-        bin.push((body.answers >> 8) as u8);
-        bin.push(body.answers as u8);
-        bin.push((body.authority_rrs >> 8) as u8);
-        bin.push(body.authority_rrs as u8);
-        bin.push((body.additional_rrs >> 8) as u8);
-        bin.push(body.additional_rrs as u8);
-
-        // Question section
-        for part in body.query.0.split('.') {
-            bin.push(part.len() as u8);
-            bin.extend_from_slice(part.as_bytes());
-        }
-        bin.push(0); // Null byte to terminate the QNAME
-        bin.push((body.query.1 >> 8) as u8);
-        bin.push(body.query.1 as u8);
-        bin.push((body.query.2 >> 8) as u8);
-        bin.push(body.query.2 as u8);
-
-        // Answer section
-        for record in body.answer_records {
-            self.binarize_record(&record, &mut bin);
+/// Reads one length-prefixed DNS message per RFC 1035 section 4.2.2 and
+/// answers it, looping until the client closes the connection. Unlike UDP,
+/// TCP responses are never truncated.
+fn handle_tcp_client(mut stream: TcpStream, resolver: Arc<ResolverPool>, cache: Cache, zones: Arc<ZoneStore>) {
+    loop {
+        let mut len_buf = [0u8; 2];
+        if stream.read_exact(&mut len_buf).is_err() {
+            return;
         }
+        let msg_len = u16::from_be_bytes(len_buf) as usize;
 
-        // Authority section
-        for record in body.authority_records {
-            self.binarize_record(&record, &mut bin);
+        let mut buf = vec![0u8; msg_len];
+        if stream.read_exact(&mut buf).is_err() {
+            return;
         }
 
-        // Additional section
-        for record in body.additional_records {
-            self.binarize_record(&record, &mut bin);
+        let mut request = DnsRequest::new();
+        if let Err(e) = request.parse(&buf) {
+            eprintln!("Dropping malformed TCP packet: {}", e);
+            return;
         }
 
-//        self.response_data = bin.clone();
-        self.response_data = bin;
-}
+        let cache_key = CacheKey::new(&request.qname, request.qtype, request.qclass);
+        let response = resolve_query(&request, &cache_key, &resolver, &cache, &zones);
 
-    fn binarize_record(&self, record: &DnsResourceRecord, bin: &mut Vec<u8>) {
-        // Name
-        for part in record.name.split('.') {
-            bin.push(part.len() as u8);
-            bin.extend_from_slice(part.as_bytes());
+        let len_prefix = (response.len() as u16).to_be_bytes();
+        if stream.write_all(&len_prefix).is_err() || stream.write_all(&response).is_err() {
+            return;
         }
-        bin.push(0); // Null byte to terminate the NAME
-
-        // Type
-        bin.push((record.rr_type >> 8) as u8);
-        bin.push(record.rr_type as u8);
-
-        // Class
-        bin.push((record.rr_class >> 8) as u8);
-        bin.push(record.rr_class as u8);
-
-        // TTL
-        bin.push((record.ttl >> 24) as u8);
-        bin.push((record.ttl >> 16) as u8);
-        bin.push((record.ttl >> 8) as u8);
-        bin.push(record.ttl as u8);
-
-        // RDLENGTH
-        bin.push((record.rdlength >> 8) as u8);
-        bin.push(record.rdlength as u8);
-
-        // RDATA
-        bin.extend_from_slice(&record.rdata);
     }
 }
 
-
-
-
-type Cache = Arc<Mutex<HashMap<String, Vec<u8>>>>;
-
-async fn remove_expired_cache(cache: Cache) {
-    loop {
-
-        task::sleep(Duration::from_secs(1)).await;
-        let mut cache = cache.lock().unwrap();
-        let keys: Vec<String> = cache.keys().cloned().collect();
-        let now = Instant::now();
-
-        for key in keys {
-            let cached_response = cache.get(&key).cloned();
-            if let Some(response_data) = cached_response {
-                let mut request = DnsRequest::new();
-                request.parse_response(&response_data);
-                let response_data = request.parse_response_data();
-
-                let expired = response_data.answer_records.iter().any(|record| record.ttl == 10);
-                if expired {
-                    cache.remove(&key);
-                    println!("Took: {:?}", now.elapsed());
-
-                }
+fn run_tcp_listener(cache: Cache, resolver: Arc<ResolverPool>, zones: Arc<ZoneStore>) {
+    let listener = TcpListener::bind("0.0.0.0:53").expect("Couldn't bind TCP listener");
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let cache = Arc::clone(&cache);
+                let resolver = Arc::clone(&resolver);
+                let zones = Arc::clone(&zones);
+                thread::spawn(move || handle_tcp_client(stream, resolver, cache, zones));
             }
+            Err(e) => eprintln!("TCP accept error: {}", e),
         }
     }
 }
 
 fn main() {
     let socket = UdpSocket::bind("0.0.0.0:53").unwrap();
-    let google_socket = UdpSocket::bind("0.0.0.0:0").expect("Couldn't bind to Google DNS socket");
+    let resolver = Arc::new(ResolverPool::new(vec![
+        Upstream::udp(SocketAddrV4::new(Ipv4Addr::new(1, 1, 1, 1), 53).into()),
+        Upstream::udp(SocketAddrV4::new(Ipv4Addr::new(8, 8, 8, 8), 53).into()),
+        Upstream::tls("1.1.1.1", 853),
+        Upstream::https("https://cloudflare-dns.com/dns-query"),
+    ], UPSTREAM_TIMEOUT));
     let cache: Cache = Arc::new(Mutex::new(HashMap::new()));
     let cache_clone = Arc::clone(&cache);
 
+    let zones = Arc::new(match ZoneStore::load(ZONE_FILE_PATH) {
+        Ok(zones) => zones,
+        Err(e) => {
+            eprintln!("Not serving any local zones ({}: {})", ZONE_FILE_PATH, e);
+            ZoneStore::empty()
+        }
+    });
+
     // Start the cache invalidation task
     task::spawn(remove_expired_cache(cache_clone));
 
-
-
-
-    let mut testReq: DnsRequest = {
-        let mut req = DnsRequest::new();
-        req.id = 0x1234;
-        req.qr = 1;
-        req.opcode = 0;
-        req.aa = 0;
-        req.tc = 0;
-        req.rd = 1;
-        req.ra = 0;
-        req.z = 0;
-        req.rcode = 0;
-        req.qdcount = 1;
-        req.ancount = 2;
-        req.nscount = 0;
-        req.arcount = 0;
-        req.qname = String::from("luca.civ.dev");
-        req.qtype = 1;
-        req.response_data = Vec::new();
-        req.qclass = 1;
-        req
-    };
-    
-    // initialize a DnsResponseData struct
-    let dnsRes =   DnsResponseData { id: 55804, 
-        flags: 129,
-         questions: 1,
-          answers: 1,
-           authority_rrs: 0,
-            additional_rrs: 0,
-             query: ("luca.civ.dev".to_string(), 1, 1),
-              answer_records: 
-              vec![DnsResourceRecord 
-              { name: String::from("luca.civ.dev"),
-               rr_type: 1,
-                rr_class: 1
-                , ttl: 10,
-                 rdlength: 4,
-                  rdata: vec![69, 69, 14, 88] }], authority_records: vec![], additional_records: vec![] 
-            };
-        
-    // binarize the DnsResponseData struct
-    testReq.binarize_response_data(dnsRes);
-
-
-    {
-        let mut cache = cache.lock().unwrap();
-        cache.insert(String::from("luca.civ.dev"), testReq.response_data);
-    }
-
-//    remove_expired_cache(frequently_used.clone()).await;
-
-    let mut i: u32 = 0;
+    // Start the TCP listener so large answers (or UDP responses we had to
+    // truncate) can be served over TCP too.
+    let tcp_cache = Arc::clone(&cache);
+    let tcp_resolver = Arc::clone(&resolver);
+    let tcp_zones = Arc::clone(&zones);
+    thread::spawn(move || run_tcp_listener(tcp_cache, tcp_resolver, tcp_zones));
 
     loop {
-        let start = Instant::now();
-
-        println!("Iteration: {}", i);
-        i += 1;
-        let mut buf = [0; 512];
+        let mut buf = [0; 4096];
         let (amt, src) = socket.recv_from(&mut buf).expect("Couldn't receive from client");
-        println!("{}", src);
         let buf = &mut buf[..amt];
         let mut request = DnsRequest::new();
-        
-        request.parse(buf);
-        let response = {
-            let mut cache = cache.lock().unwrap();
-            match cache.get(&request.qname) {
-                Some(cached_response) => {
-                    let mut cached_request = DnsRequest::new();
-                    cached_request.parse_response(cached_response);
-
-                    cached_request.id = request.id;
-                    cached_request.binarize()
-                }
-                None => {
-                    let mut res_buf = [0; 512];
-                    let google_addr = SocketAddrV4::new(Ipv4Addr::new(1, 1, 1, 1), 53);
-                    google_socket.send_to(&request.binarize(), google_addr).expect("Couldn't send to Google DNS");
-
-                    let (res_amt, _) = google_socket.recv_from(&mut res_buf).expect("Couldn't receive from Google DNS");
-
-                    let response = res_buf[..res_amt].to_vec();
-                    cache.insert(request.qname.clone(), response.clone());
-
-                    response
-                }
-            }
+
+        if let Err(e) = request.parse(buf) {
+            eprintln!("Dropping malformed packet from {}: {}", src, e);
+            continue;
+        }
+        let cache_key = CacheKey::new(&request.qname, request.qtype, request.qclass);
+        let response = resolve_query(&request, &cache_key, &resolver, &cache, &zones);
+
+        let max_udp_size = client_udp_payload_size(&request, buf) as usize;
+        let response = if response.len() > max_udp_size {
+            truncate_for_udp(&response, max_udp_size)
+        } else {
+            response
         };
         socket.send_to(&response, src).expect("Couldn't send response to original client");
 
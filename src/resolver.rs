@@ -0,0 +1,194 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream, UdpSocket};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Caps how much of a single DNS-over-HTTPS response body we'll buffer into
+/// memory, so it is bounded like the UDP transport's 4096-byte buffer and
+/// the TLS transport's 2-byte length prefix (at most 65535 bytes) are.
+const MAX_DOH_RESPONSE_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug)]
+pub enum ResolverError {
+    Io(io::Error),
+    NoUpstreamsConfigured,
+}
+
+impl fmt::Display for ResolverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolverError::Io(e) => write!(f, "{}", e),
+            ResolverError::NoUpstreamsConfigured => write!(f, "no upstream resolvers configured"),
+        }
+    }
+}
+
+impl std::error::Error for ResolverError {}
+
+impl From<io::Error> for ResolverError {
+    fn from(e: io::Error) -> ResolverError {
+        ResolverError::Io(e)
+    }
+}
+
+/// The wire transport used to reach an upstream resolver.
+#[derive(Debug, Clone)]
+pub enum Transport {
+    /// Plain DNS over UDP (RFC 1035).
+    Udp { addr: SocketAddr },
+    /// DNS-over-TLS (RFC 7858), conventionally port 853.
+    Tls { host: String, port: u16 },
+    /// DNS-over-HTTPS (RFC 8484): the wire-format query is POSTed to `url`
+    /// with `Content-Type: application/dns-message`.
+    Https { url: String },
+}
+
+#[derive(Debug, Clone)]
+pub struct Upstream {
+    pub transport: Transport,
+}
+
+impl Upstream {
+    pub fn udp(addr: SocketAddr) -> Upstream {
+        Upstream { transport: Transport::Udp { addr } }
+    }
+
+    pub fn tls(host: &str, port: u16) -> Upstream {
+        Upstream { transport: Transport::Tls { host: host.to_string(), port } }
+    }
+
+    pub fn https(url: &str) -> Upstream {
+        Upstream { transport: Transport::Https { url: url.to_string() } }
+    }
+
+    fn query(&self, query: &[u8], timeout: Duration) -> Result<Vec<u8>, ResolverError> {
+        match &self.transport {
+            Transport::Udp { addr } => query_udp(*addr, query, timeout),
+            Transport::Tls { host, port } => query_tls(host, *port, query, timeout),
+            Transport::Https { url } => query_https(url, query, timeout),
+        }
+    }
+}
+
+/// Sends `query` to `addr` over UDP and waits for a reply, rejecting any
+/// packet that doesn't come from `addr` or doesn't carry the transaction ID
+/// we sent — since the socket isn't `connect`ed, any host able to reach this
+/// ephemeral port during the timeout window could otherwise spoof a reply
+/// and poison the cache. Non-matching packets are discarded and we keep
+/// waiting out the remaining timeout rather than failing on the first one.
+fn query_udp(addr: SocketAddr, query: &[u8], timeout: Duration) -> Result<Vec<u8>, ResolverError> {
+    let socket = UdpSocket::bind("0.0.0.0:0")?;
+    socket.set_write_timeout(Some(timeout))?;
+    socket.send_to(query, addr)?;
+
+    let expected_id = (query[0] as u16) << 8 | query[1] as u16;
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(ResolverError::Io(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "timed out waiting for a reply matching the query's upstream and transaction ID",
+            )));
+        }
+        socket.set_read_timeout(Some(remaining))?;
+
+        let mut buf = [0u8; 4096];
+        let (amt, from) = socket.recv_from(&mut buf)?;
+        if from != addr || amt < 2 || (buf[0] as u16) << 8 | buf[1] as u16 != expected_id {
+            continue;
+        }
+        return Ok(buf[..amt].to_vec());
+    }
+}
+
+/// Speaks DNS-over-TLS: same 2-byte length-prefixed framing as plain TCP
+/// DNS (RFC 1035 section 4.2.2), just inside a TLS session.
+fn query_tls(host: &str, port: u16, query: &[u8], timeout: Duration) -> Result<Vec<u8>, ResolverError> {
+    let tcp = TcpStream::connect((host, port))?;
+    tcp.set_read_timeout(Some(timeout))?;
+    tcp.set_write_timeout(Some(timeout))?;
+
+    let connector = native_tls::TlsConnector::new().map_err(|e| ResolverError::Io(io::Error::other(e)))?;
+    let mut tls = connector.connect(host, tcp).map_err(|e| ResolverError::Io(io::Error::other(e)))?;
+
+    tls.write_all(&(query.len() as u16).to_be_bytes())?;
+    tls.write_all(query)?;
+
+    let mut len_buf = [0u8; 2];
+    tls.read_exact(&mut len_buf)?;
+    let mut response = vec![0u8; u16::from_be_bytes(len_buf) as usize];
+    tls.read_exact(&mut response)?;
+    Ok(response)
+}
+
+/// Speaks DNS-over-HTTPS (RFC 8484) using the wire-format POST variant.
+fn query_https(url: &str, query: &[u8], timeout: Duration) -> Result<Vec<u8>, ResolverError> {
+    let response = ureq::post(url)
+        .timeout(timeout)
+        .set("Content-Type", "application/dns-message")
+        .send_bytes(query)
+        .map_err(|e| ResolverError::Io(io::Error::other(e.to_string())))?;
+
+    // Read one byte past the cap so an oversized body can be distinguished
+    // from one that lands exactly on the limit, instead of trusting
+    // whatever length the upstream claims and buffering it unbounded.
+    let mut body = Vec::new();
+    response.into_reader().take(MAX_DOH_RESPONSE_BYTES + 1).read_to_end(&mut body)?;
+
+    if body.len() as u64 > MAX_DOH_RESPONSE_BYTES {
+        return Err(ResolverError::Io(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "DoH response exceeded the maximum allowed size",
+        )));
+    }
+
+    Ok(body)
+}
+
+/// A pool of upstream resolvers queried round-robin, with failover to the
+/// next upstream on timeout or error so one dead/slow forwarder can't stall
+/// every query.
+pub struct ResolverPool {
+    upstreams: Vec<Upstream>,
+    next: AtomicUsize,
+    timeout: Duration,
+}
+
+impl ResolverPool {
+    pub fn new(upstreams: Vec<Upstream>, timeout: Duration) -> ResolverPool {
+        ResolverPool {
+            upstreams,
+            next: AtomicUsize::new(0),
+            timeout,
+        }
+    }
+
+    /// Sends `query` to upstreams in round-robin order, each query starting
+    /// one position past where the previous query started (regardless of
+    /// which upstream ended up answering it), trying the next one in the
+    /// pool whenever the current one times out or errors.
+    pub fn resolve(&self, query: &[u8]) -> Result<Vec<u8>, ResolverError> {
+        if self.upstreams.is_empty() {
+            return Err(ResolverError::NoUpstreamsConfigured);
+        }
+
+        let start = self.next.fetch_add(1, Ordering::Relaxed) % self.upstreams.len();
+        let mut last_err = ResolverError::NoUpstreamsConfigured;
+
+        for i in 0..self.upstreams.len() {
+            let upstream = &self.upstreams[(start + i) % self.upstreams.len()];
+            match upstream.query(query, self.timeout) {
+                Ok(response) => return Ok(response),
+                Err(e) => {
+                    eprintln!("Upstream {:?} failed: {}", upstream.transport, e);
+                    last_err = e;
+                }
+            }
+        }
+
+        Err(last_err)
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::io;
+
+use crate::dns::{DnsRequest, DnsResourceRecord, DnsResponseData, RData, RecordType};
+
+#[derive(Debug)]
+pub enum ZoneError {
+    Io(io::Error),
+    Syntax { line: usize, message: String },
+}
+
+impl fmt::Display for ZoneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ZoneError::Io(e) => write!(f, "{}", e),
+            ZoneError::Syntax { line, message } => write!(f, "line {}: {}", line, message),
+        }
+    }
+}
+
+impl std::error::Error for ZoneError {}
+
+impl From<io::Error> for ZoneError {
+    fn from(e: io::Error) -> ZoneError {
+        ZoneError::Io(e)
+    }
+}
+
+fn syntax(line: usize, message: impl Into<String>) -> ZoneError {
+    ZoneError::Syntax { line, message: message.into() }
+}
+
+/// A single resource record belonging to a zone. `Ord` is derived purely so
+/// records can live in a `BTreeSet` (for stable, deduplicated iteration);
+/// the ordering has no protocol meaning.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ZoneRecord {
+    pub name: String,
+    pub rr_type: u16,
+    pub rr_class: u16,
+    pub ttl: u32,
+    pub rdata: RData,
+}
+
+/// A locally-served zone: its SOA fields plus every record configured under
+/// it. Answers authoritatively instead of being forwarded upstream.
+#[derive(Debug, Clone)]
+pub struct Zone {
+    pub domain: String,
+    pub mname: String,
+    pub rname: String,
+    pub serial: u32,
+    pub refresh: u32,
+    pub retry: u32,
+    pub expire: u32,
+    pub minimum: u32,
+    pub records: BTreeSet<ZoneRecord>,
+}
+
+impl Zone {
+    fn soa_record(&self) -> DnsResourceRecord {
+        DnsResourceRecord {
+            name: self.domain.clone(),
+            rr_type: RecordType::Soa.to_u16(),
+            rr_class: 1,
+            ttl: self.minimum,
+            rdata: RData::Soa {
+                mname: self.mname.clone(),
+                rname: self.rname.clone(),
+                serial: self.serial,
+                refresh: self.refresh,
+                retry: self.retry,
+                expire: self.expire,
+                minimum: self.minimum,
+            },
+        }
+    }
+
+    /// Answers `request` authoritatively from this zone's records: matching
+    /// answers when present (RFC 1035 section 4.3.2), otherwise NODATA or
+    /// NXDOMAIN with this zone's SOA in the authority section per RFC 1035
+    /// section 6.2.1 / RFC 2308.
+    pub fn answer(&self, request: &DnsRequest) -> DnsResponseData {
+        let qname = request.qname.to_ascii_lowercase();
+
+        let answer_records: Vec<DnsResourceRecord> = self.records.iter()
+            .filter(|r| r.name.eq_ignore_ascii_case(&qname) && r.rr_type == request.qtype)
+            .map(|r| DnsResourceRecord {
+                name: r.name.clone(),
+                rr_type: r.rr_type,
+                rr_class: r.rr_class,
+                ttl: r.ttl,
+                rdata: r.rdata.clone(),
+            })
+            .collect();
+
+        let name_exists = self.records.iter().any(|r| r.name.eq_ignore_ascii_case(&qname));
+
+        let (rcode, authority_records) = if !answer_records.is_empty() {
+            (0, vec![])
+        } else if name_exists {
+            (0, vec![self.soa_record()]) // NODATA: name exists, not this qtype
+        } else {
+            (3, vec![self.soa_record()]) // NXDOMAIN
+        };
+
+        DnsResponseData {
+            id: request.id,
+            flags: (1 << 7) | (1 << 2) | (request.opcode << 3) | request.rd, // QR + AA
+            ra: 1, // this server also recurses for non-local names
+            z: 0,
+            rcode,
+            questions: 1,
+            answers: answer_records.len() as u16,
+            authority_rrs: authority_records.len() as u16,
+            additional_rrs: 0,
+            query: (request.qname.clone(), request.qtype, request.qclass),
+            answer_records,
+            authority_records,
+            additional_records: vec![],
+        }
+    }
+}
+
+fn parse_record_type(name: &str, line: usize) -> Result<RecordType, ZoneError> {
+    match name.to_ascii_uppercase().as_str() {
+        "A" => Ok(RecordType::A),
+        "NS" => Ok(RecordType::Ns),
+        "CNAME" => Ok(RecordType::Cname),
+        "PTR" => Ok(RecordType::Ptr),
+        "MX" => Ok(RecordType::Mx),
+        "TXT" => Ok(RecordType::Txt),
+        "AAAA" => Ok(RecordType::Aaaa),
+        "SRV" => Ok(RecordType::Srv),
+        other => Err(syntax(line, format!("unsupported record type '{}'", other))),
+    }
+}
+
+fn parse_rdata(rr_type: RecordType, tokens: &[&str], line: usize) -> Result<RData, ZoneError> {
+    match rr_type {
+        RecordType::A => {
+            let addr = tokens.first().ok_or_else(|| syntax(line, "A record missing an address"))?;
+            addr.parse().map(RData::A).map_err(|_| syntax(line, format!("invalid IPv4 address '{}'", addr)))
+        }
+        RecordType::Aaaa => {
+            let addr = tokens.first().ok_or_else(|| syntax(line, "AAAA record missing an address"))?;
+            addr.parse().map(RData::Aaaa).map_err(|_| syntax(line, format!("invalid IPv6 address '{}'", addr)))
+        }
+        RecordType::Ns => Ok(RData::Ns(require_token(tokens, 0, line, "NS record missing a target")?)),
+        RecordType::Cname => Ok(RData::Cname(require_token(tokens, 0, line, "CNAME record missing a target")?)),
+        RecordType::Ptr => Ok(RData::Ptr(require_token(tokens, 0, line, "PTR record missing a target")?)),
+        RecordType::Mx => {
+            let preference = tokens.first().ok_or_else(|| syntax(line, "MX record missing a preference"))?;
+            let preference: u16 = preference.parse().map_err(|_| syntax(line, format!("invalid MX preference '{}'", preference)))?;
+            let exchange = require_token(tokens, 1, line, "MX record missing an exchange")?;
+            Ok(RData::Mx { preference, exchange })
+        }
+        RecordType::Txt => {
+            if tokens.is_empty() {
+                return Err(syntax(line, "TXT record missing a value"));
+            }
+            Ok(RData::Txt(vec![tokens.join(" ")]))
+        }
+        RecordType::Srv => {
+            if tokens.len() < 4 {
+                return Err(syntax(line, "SRV record needs priority, weight, port and target"));
+            }
+            let priority: u16 = tokens[0].parse().map_err(|_| syntax(line, format!("invalid SRV priority '{}'", tokens[0])))?;
+            let weight: u16 = tokens[1].parse().map_err(|_| syntax(line, format!("invalid SRV weight '{}'", tokens[1])))?;
+            let port: u16 = tokens[2].parse().map_err(|_| syntax(line, format!("invalid SRV port '{}'", tokens[2])))?;
+            Ok(RData::Srv { priority, weight, port, target: tokens[3].to_string() })
+        }
+        RecordType::Soa | RecordType::Opt | RecordType::Other(_) => {
+            Err(syntax(line, "record type not supported in zone files"))
+        }
+    }
+}
+
+fn require_token(tokens: &[&str], index: usize, line: usize, message: &str) -> Result<String, ZoneError> {
+    tokens.get(index).map(|s| s.to_string()).ok_or_else(|| syntax(line, message))
+}
+
+fn parse_record(rest: &str, line: usize) -> Result<ZoneRecord, ZoneError> {
+    let mut parts = rest.split_whitespace();
+    let name = parts.next().ok_or_else(|| syntax(line, "record missing a name"))?.to_string();
+    let rr_type_name = parts.next().ok_or_else(|| syntax(line, "record missing a type"))?;
+    let ttl = parts.next().ok_or_else(|| syntax(line, "record missing a TTL"))?;
+    let ttl: u32 = ttl.parse().map_err(|_| syntax(line, format!("invalid TTL '{}'", ttl)))?;
+    let tokens: Vec<&str> = parts.collect();
+
+    let rr_type = parse_record_type(rr_type_name, line)?;
+    let rdata = parse_rdata(rr_type, &tokens, line)?;
+
+    Ok(ZoneRecord {
+        name,
+        rr_type: rr_type.to_u16(),
+        rr_class: 1,
+        ttl,
+        rdata,
+    })
+}
+
+fn parse_u32(value: &str, line: usize) -> Result<u32, ZoneError> {
+    value.parse().map_err(|_| syntax(line, format!("invalid number '{}'", value)))
+}
+
+/// In-memory store of configured zones, keyed by lowercased domain.
+pub struct ZoneStore {
+    zones: HashMap<String, Zone>,
+}
+
+impl ZoneStore {
+    pub fn empty() -> ZoneStore {
+        ZoneStore { zones: HashMap::new() }
+    }
+
+    /// Loads zone definitions from a line-oriented file: blank lines and
+    /// `#`-comments are ignored, every other line is `<directive>: <value>`.
+    /// A `zone:` line starts a new zone (ending whichever one preceded it);
+    /// `mname`/`rname`/`serial`/`refresh`/`retry`/`expire`/`minimum` set its
+    /// SOA fields, and each `record:` line is `<name> <type> <ttl> <rdata...>`,
+    /// e.g. `record: example.com A 300 93.184.216.34`.
+    pub fn load(path: &str) -> Result<ZoneStore, ZoneError> {
+        let contents = fs::read_to_string(path)?;
+        let mut zones = HashMap::new();
+        let mut current: Option<Zone> = None;
+
+        for (i, raw_line) in contents.lines().enumerate() {
+            let line_no = i + 1;
+            let line = raw_line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (directive, rest) = line.split_once(':')
+                .ok_or_else(|| syntax(line_no, format!("expected '<directive>: <value>', got {:?}", line)))?;
+            let directive = directive.trim();
+            let rest = rest.trim();
+
+            if directive == "zone" {
+                if let Some(zone) = current.take() {
+                    zones.insert(zone.domain.to_ascii_lowercase(), zone);
+                }
+                current = Some(Zone {
+                    domain: rest.to_string(),
+                    mname: String::new(),
+                    rname: String::new(),
+                    serial: 0,
+                    refresh: 0,
+                    retry: 0,
+                    expire: 0,
+                    minimum: 0,
+                    records: BTreeSet::new(),
+                });
+                continue;
+            }
+
+            let zone = current.as_mut()
+                .ok_or_else(|| syntax(line_no, format!("'{}' directive before any 'zone:' line", directive)))?;
+
+            match directive {
+                "mname" => zone.mname = rest.to_string(),
+                "rname" => zone.rname = rest.to_string(),
+                "serial" => zone.serial = parse_u32(rest, line_no)?,
+                "refresh" => zone.refresh = parse_u32(rest, line_no)?,
+                "retry" => zone.retry = parse_u32(rest, line_no)?,
+                "expire" => zone.expire = parse_u32(rest, line_no)?,
+                "minimum" => zone.minimum = parse_u32(rest, line_no)?,
+                "record" => {
+                    zone.records.insert(parse_record(rest, line_no)?);
+                }
+                other => return Err(syntax(line_no, format!("unknown directive '{}'", other))),
+            }
+        }
+
+        if let Some(zone) = current.take() {
+            zones.insert(zone.domain.to_ascii_lowercase(), zone);
+        }
+
+        Ok(ZoneStore { zones })
+    }
+
+    /// Finds the most specific configured zone `qname` falls within, walking
+    /// up through parent labels (`www.example.com` -> `example.com` -> `com`)
+    /// the way delegation works in the real DNS tree.
+    pub fn find(&self, qname: &str) -> Option<&Zone> {
+        let qname = qname.to_ascii_lowercase();
+        let mut suffix = qname.as_str();
+        loop {
+            if let Some(zone) = self.zones.get(suffix) {
+                return Some(zone);
+            }
+            match suffix.split_once('.') {
+                Some((_, rest)) => suffix = rest,
+                None => return None,
+            }
+        }
+    }
+}